@@ -1,5 +1,7 @@
 use std::{
     future::Future,
+    io,
+    net::SocketAddr,
     pin::Pin,
     sync::{Arc, Mutex},
     task::{Context, Poll, Waker},
@@ -8,13 +10,22 @@ use std::{
 use bytes::{Buf, Bytes};
 use futures::{
     channel::{mpsc, oneshot},
-    future, FutureExt, SinkExt, StreamExt,
+    future, ready, FutureExt, SinkExt, StreamExt,
 };
 use thiserror::Error;
-use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, DuplexStream};
+use tokio::{
+    io::{AsyncBufRead, AsyncRead, AsyncWrite, AsyncWriteExt, DuplexStream, ReadBuf},
+    net::TcpListener,
+    sync::Semaphore,
+    task::JoinHandle,
+};
 use tokio_tungstenite::{tungstenite as ws, WebSocketStream};
 use tokio_util::io::ReaderStream;
 
+/// Default size, in bytes, of a [`BufferedPortStream`]'s read buffer and write-coalescing
+/// threshold.
+const DEFAULT_BUFFER_SIZE: usize = 8 * 1024;
+
 /// Errors from Portforwarder.
 #[derive(Debug, Error)]
 pub enum Error {
@@ -62,6 +73,27 @@ pub enum Error {
     /// Failed to receive a WebSocket message from the server.
     #[error("failed to receive a WebSocket message: {0}")]
     ReceiveWebSocketMessage(#[source] ws::Error),
+
+    /// The requested port is not one of the ports being forwarded.
+    #[error("port {0} is not being forwarded")]
+    PortNotForwarded(u16),
+
+    /// The stream of a [`Port`] had already been taken, e.g. one returned by a [`SessionFactory`]
+    /// or passed to [`Portforwarder::bind_local`].
+    #[error("stream for port {0} was already taken")]
+    PortStreamTaken(u16),
+
+    /// Failed to bind a local TCP listener for a forwarded port.
+    #[error("failed to bind local port: {0}")]
+    Bind(#[source] std::io::Error),
+
+    /// Failed to accept a connection on a local TCP listener.
+    #[error("failed to accept local connection: {0}")]
+    Accept(#[source] std::io::Error),
+
+    /// Failed to forward bytes between a local connection and the forwarded port.
+    #[error("failed to forward bytes between local connection and pod: {0}")]
+    LocalForward(#[source] std::io::Error),
 }
 
 type ErrorReceiver = oneshot::Receiver<String>;
@@ -71,6 +103,10 @@ type ErrorSender = oneshot::Sender<String>;
 enum Message {
     FromPod(u8, Bytes),
     ToPod(u8, Bytes),
+    // All `to_pod_loop`s have hit EOF; half-close by sending a WebSocket `Close` frame.
+    ToPodDone,
+    // The server sent a `Close` frame, or the `Portforwarder` was aborted; tear down the session.
+    Shutdown,
 }
 
 struct PortforwarderState {
@@ -78,13 +114,64 @@ struct PortforwarderState {
     result: Option<Result<(), Error>>,
 }
 
-// Provides `AsyncRead + AsyncWrite` for each port and **does not** bind to local ports.
-// Error channel for each port is only written by the server when there's an exception and
-// the port cannot be used (didn't initialize or can't be used anymore).
+/// Options controlling a [`Portforwarder`]'s duplex buffers and internal backpressure.
+///
+/// `forwarder_loop` writes data from the pod into a port's duplex buffer; once that buffer is full
+/// because the caller is not reading it, those writes block, and while they do no further data is
+/// read from the pod for any port. Writes by the caller block once the duplex buffer in the other
+/// direction is full, i.e. while data cannot be sent on to the pod as fast as it is written. A
+/// larger `buffer_size` tolerates more of that skew at the cost of memory per port, which matters
+/// when forwarding many ports at once. `channel_capacity` bounds how far the WebSocket-reading
+/// loop and the per-port loops reading the caller's writes can run ahead of `forwarder_loop`
+/// before they block.
+#[derive(Debug, Clone, Copy)]
+pub struct PortforwardOptions {
+    /// Size in bytes of each port's duplex pipe. Defaults to `1024 * 1024`.
+    pub buffer_size: usize,
+    /// Capacity of the internal control channel shared by all ports. Defaults to `1`.
+    pub channel_capacity: usize,
+}
+
+impl Default for PortforwardOptions {
+    fn default() -> Self {
+        PortforwardOptions {
+            buffer_size: 1024 * 1024,
+            channel_capacity: 1,
+        }
+    }
+}
+
+// Sends the session's abort signal, once, either because `abort()` was called explicitly or
+// because every clone of the `Arc` wrapping this has been dropped. Shared between `Portforwarder`
+// and every `Port` it produced, so a `Port` taken out via `Portforwarder::take_port` keeps the
+// session alive on its own after the `Portforwarder` that created it is gone.
+struct SessionGuard {
+    abort: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+impl SessionGuard {
+    fn abort(&self) {
+        if let Some(abort) = self.abort.lock().unwrap().take() {
+            // Ignore the error: it only means the session already shut down on its own.
+            let _ = abort.send(());
+        }
+    }
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        self.abort();
+    }
+}
+
+// Provides `AsyncRead + AsyncWrite` for each port; local TCP listeners are opt-in via
+// `bind_local`. Error channel for each port is only written by the server when there's an
+// exception and the port cannot be used (didn't initialize or can't be used anymore).
 /// Manage port forwarding.
 pub struct Portforwarder {
     ports: Vec<Port>,
     state: Arc<Mutex<PortforwarderState>>,
+    guard: Arc<SessionGuard>,
 }
 
 impl Portforwarder {
@@ -92,13 +179,34 @@ impl Portforwarder {
     where
         S: AsyncRead + AsyncWrite + Unpin + Sized + Send + 'static,
     {
+        Self::new_with_options(stream, port_nums, PortforwardOptions::default())
+    }
+
+    // Like `new`, but with caller-supplied `PortforwardOptions` instead of
+    // `PortforwardOptions::default`. Crate-private for the same reason `new` is: building a
+    // `WebSocketStream<S>` that actually negotiates the apiserver's port-forward subprotocol is
+    // the crate's job, not a downstream caller's. Exposing `PortforwardOptions` to consumers means
+    // threading it through the crate's real public constructor, not widening this one.
+    pub(crate) fn new_with_options<S>(
+        stream: WebSocketStream<S>,
+        port_nums: &[u16],
+        options: PortforwardOptions,
+    ) -> Self
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Sized + Send + 'static,
+    {
+        let (abort_tx, abort_rx) = oneshot::channel();
+        let guard = Arc::new(SessionGuard {
+            abort: Mutex::new(Some(abort_tx)),
+        });
+
         let mut ports = Vec::new();
         let mut errors = Vec::new();
         let mut duplexes = Vec::new();
-        for _ in port_nums.iter() {
-            let (a, b) = tokio::io::duplex(1024 * 1024);
+        for port in port_nums.iter() {
+            let (a, b) = tokio::io::duplex(options.buffer_size);
             let (tx, rx) = oneshot::channel();
-            ports.push(Port::new(a, rx));
+            ports.push(Port::new(*port, a, rx, guard.clone()));
             errors.push(Some(tx));
             duplexes.push(b);
         }
@@ -109,8 +217,17 @@ impl Portforwarder {
         }));
         let shared_state = state.clone();
         let port_nums = port_nums.to_owned();
+        let channel_capacity = options.channel_capacity;
         tokio::spawn(async move {
-            let result = start_message_loop(stream, port_nums, duplexes, errors).await;
+            let result = start_message_loop(
+                stream,
+                port_nums,
+                duplexes,
+                errors,
+                channel_capacity,
+                abort_rx,
+            )
+            .await;
 
             let mut shared = shared_state.lock().unwrap();
             shared.result = Some(result);
@@ -118,13 +235,251 @@ impl Portforwarder {
                 waker.wake()
             }
         });
-        Portforwarder { ports, state }
+        Portforwarder {
+            ports,
+            state,
+            guard,
+        }
     }
 
     /// Get streams for forwarded ports.
     pub fn ports(&mut self) -> &mut [Port] {
         self.ports.as_mut_slice()
     }
+
+    /// Move the [`Port`] for `port` out of this `Portforwarder` by value.
+    ///
+    /// Unlike [`Portforwarder::ports`], which only ever hands back a borrow, this lets a caller
+    /// (e.g. a [`SessionFactory`] closure) return the `Port` on its own once the `Portforwarder`
+    /// that produced it goes out of scope. After this call, `port` is no longer reachable via
+    /// [`Portforwarder::ports`] or [`Portforwarder::bind_local`].
+    pub fn take_port(&mut self, port: u16) -> Option<Port> {
+        let index = self.ports.iter().position(|p| p.number == port)?;
+        Some(self.ports.remove(index))
+    }
+
+    /// Proactively close the underlying WebSocket connection, releasing the server-side
+    /// port-forward session without waiting for the pod, local callers, or any [`Port`] taken
+    /// out via [`Portforwarder::take_port`] to finish.
+    ///
+    /// Dropping the `Portforwarder` has the same effect, but only once every `Port` it produced
+    /// has also been dropped; call this directly to close the session immediately while still
+    /// holding on to the value (e.g. to await its `Future`).
+    pub fn abort(&mut self) {
+        self.guard.abort();
+    }
+
+    /// Bind a local TCP listener on `127.0.0.1:<port>` and pump traffic between the first
+    /// accepted connection and the forwarded `port`, turning the raw [`Port::stream`] channel
+    /// into a drop-in replacement for `kubectl port-forward`.
+    ///
+    /// A `Port`'s duplex stream represents exactly one upstream pod connection for the life of
+    /// the session, so only a single local client is served: once it disconnects, the pod-side
+    /// stream is torn down along with it and the listener stops. For many concurrent local
+    /// clients (e.g. a forwarded database), use [`PortForwardMultiplexer`] instead, which opens
+    /// a fresh upstream session per connection.
+    ///
+    /// `port` must be one of the ports this `Portforwarder` was created with, and
+    /// [`Port::stream`] must not have been taken for it already.
+    ///
+    /// Returns a [`LocalPortForwarder`] whose `join` future resolves once that connection (or
+    /// the listener itself) ends, whether it finished normally, was aborted, or returned an error.
+    pub async fn bind_local(&mut self, port: u16) -> Result<LocalPortForwarder, Error> {
+        let stream = self
+            .ports
+            .iter_mut()
+            .find(|p| p.number == port)
+            .ok_or(Error::PortNotForwarded(port))?
+            .stream()
+            .ok_or(Error::PortStreamTaken(port))?;
+
+        let addr = SocketAddr::from(([127, 0, 0, 1], port));
+        let listener = TcpListener::bind(addr).await.map_err(Error::Bind)?;
+        let local_addr = listener.local_addr().map_err(Error::Bind)?;
+
+        let handle = tokio::spawn(local_forward_loop(listener, stream));
+        Ok(LocalPortForwarder { local_addr, handle })
+    }
+}
+
+/// A background task pumping local TCP connections into a single forwarded port.
+///
+/// Returned by [`Portforwarder::bind_local`]. Dropping this without calling [`LocalPortForwarder::join`]
+/// leaves the listener running in the background; use [`LocalPortForwarder::abort`] to stop it early.
+pub struct LocalPortForwarder {
+    local_addr: SocketAddr,
+    handle: JoinHandle<Result<(), Error>>,
+}
+
+impl LocalPortForwarder {
+    /// The address the local listener is bound to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stop accepting new local connections.
+    pub fn abort(&self) {
+        self.handle.abort();
+    }
+
+    /// Wait for the listener loop to finish, returning any error it encountered.
+    pub async fn join(self) -> Result<(), Error> {
+        match self.handle.await {
+            Ok(result) => result,
+            Err(join_err) if join_err.is_cancelled() => Ok(()),
+            Err(join_err) => std::panic::resume_unwind(join_err.into_panic()),
+        }
+    }
+}
+
+// Accept a single connection on `listener` and pump it through `stream`. A `Port` only ever
+// exposes one duplex stream backed by one upstream pod connection, so unlike a typical accept
+// loop this serves exactly one local client rather than looping indefinitely: once that client
+// (or the pod) closes the stream, there is no live upstream left to hand a second client.
+async fn local_forward_loop(
+    listener: TcpListener,
+    mut stream: impl AsyncRead + AsyncWrite + Unpin,
+) -> Result<(), Error> {
+    let (mut socket, _peer_addr) = listener.accept().await.map_err(Error::Accept)?;
+    tokio::io::copy_bidirectional(&mut socket, &mut stream)
+        .await
+        .map_err(Error::LocalForward)?;
+    Ok(())
+}
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Opens a fresh port-forward session for a single port, on demand.
+///
+/// Implemented for any `Fn() -> impl Future<Output = Result<Port, Error>>`, so callers typically
+/// hand [`PortForwardMultiplexer::new`] a closure that opens a new `Portforwarder` and, once
+/// connected, hands back one of its `Port`s via [`Portforwarder::take_port`]. The `Portforwarder`
+/// itself can then be dropped at the end of the closure: the returned `Port` keeps the session
+/// alive on its own for as long as it's in use.
+pub trait SessionFactory: Send + Sync {
+    /// Open a new session and return the `Port` backing it.
+    fn open(&self) -> BoxFuture<'static, Result<Port, Error>>;
+}
+
+impl<F, Fut> SessionFactory for F
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = Result<Port, Error>> + Send + 'static,
+{
+    fn open(&self) -> BoxFuture<'static, Result<Port, Error>> {
+        Box::pin(self())
+    }
+}
+
+/// Options controlling a [`PortForwardMultiplexer`].
+#[derive(Debug, Clone, Copy)]
+pub struct PortForwardMultiplexerOptions {
+    /// Maximum number of upstream sessions open at once. Once reached, new local connections
+    /// wait for an existing one to close before a session is opened for them.
+    pub max_connections: usize,
+}
+
+impl Default for PortForwardMultiplexerOptions {
+    fn default() -> Self {
+        PortForwardMultiplexerOptions { max_connections: 8 }
+    }
+}
+
+/// Presents a single local port backed by many concurrent upstream port-forward sessions.
+///
+/// Unlike [`Portforwarder::bind_local`], which serves a single connection on one [`Port`]'s duplex
+/// stream, the multiplexer opens a fresh session (via [`SessionFactory`]) for every accepted
+/// connection, so multiple clients can talk to the forwarded port at the same time.
+pub struct PortForwardMultiplexer<F> {
+    factory: Arc<F>,
+    options: PortForwardMultiplexerOptions,
+}
+
+impl<F> PortForwardMultiplexer<F>
+where
+    F: SessionFactory + 'static,
+{
+    /// Create a multiplexer with the default [`PortForwardMultiplexerOptions`].
+    pub fn new(factory: F) -> Self {
+        Self::new_with_options(factory, PortForwardMultiplexerOptions::default())
+    }
+
+    /// Create a multiplexer with custom [`PortForwardMultiplexerOptions`].
+    pub fn new_with_options(factory: F, options: PortForwardMultiplexerOptions) -> Self {
+        PortForwardMultiplexer {
+            factory: Arc::new(factory),
+            options,
+        }
+    }
+
+    /// Bind a local TCP listener on `local_addr` and back each accepted connection with its own
+    /// upstream session opened via the [`SessionFactory`].
+    pub async fn bind_local(&self, local_addr: SocketAddr) -> Result<LocalPortForwarder, Error> {
+        let listener = TcpListener::bind(local_addr).await.map_err(Error::Bind)?;
+        let local_addr = listener.local_addr().map_err(Error::Bind)?;
+
+        let handle = tokio::spawn(multiplexed_forward_loop(
+            listener,
+            self.factory.clone(),
+            Arc::new(Semaphore::new(self.options.max_connections)),
+        ));
+        Ok(LocalPortForwarder { local_addr, handle })
+    }
+}
+
+// Accept connections on `listener`, opening a new upstream session per connection (up to
+// `semaphore`'s permit count) and tearing it down once the connection or session ends. Each
+// connection is forwarded on its own spawned task, so a slow or stuck connection can't hold up
+// the accept loop or its peers.
+async fn multiplexed_forward_loop<F>(
+    listener: TcpListener,
+    factory: Arc<F>,
+    semaphore: Arc<Semaphore>,
+) -> Result<(), Error>
+where
+    F: SessionFactory + 'static,
+{
+    loop {
+        let (socket, _peer_addr) = listener.accept().await.map_err(Error::Accept)?;
+
+        let permit = semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("semaphore is never closed");
+        let factory = factory.clone();
+        tokio::spawn(async move {
+            let _permit = permit;
+            let _ = forward_multiplexed_connection(socket, factory.as_ref()).await;
+        });
+    }
+}
+
+// Open a new session for `socket` and pump bytes between them until either side is done or the
+// session's error channel fires.
+async fn forward_multiplexed_connection<F>(
+    mut socket: tokio::net::TcpStream,
+    factory: &F,
+) -> Result<(), Error>
+where
+    F: SessionFactory,
+{
+    let mut port = factory.open().await?;
+    let mut stream = port.stream().ok_or(Error::PortStreamTaken(port.number()))?;
+    match port.error() {
+        Some(error) => {
+            tokio::select! {
+                res = tokio::io::copy_bidirectional(&mut socket, &mut stream) => {
+                    res.map(|_| ()).map_err(Error::LocalForward)
+                }
+                _ = error => Ok(()),
+            }
+        }
+        None => tokio::io::copy_bidirectional(&mut socket, &mut stream)
+            .await
+            .map(|_| ())
+            .map_err(Error::LocalForward),
+    }
 }
 
 impl Future for Portforwarder {
@@ -148,20 +503,38 @@ impl Future for Portforwarder {
 }
 
 pub struct Port {
+    // Port number being forwarded.
+    number: u16,
     // Data pipe.
     stream: Option<DuplexStream>,
     // Error channel.
     error: Option<ErrorReceiver>,
+    // Keeps the parent session alive for as long as this `Port` is, even after the
+    // `Portforwarder` that produced it has been dropped (e.g. once taken out via
+    // `Portforwarder::take_port`). Never read directly; held only for its `Drop` side effect.
+    _guard: Arc<SessionGuard>,
 }
 
 impl Port {
-    pub(crate) fn new(stream: DuplexStream, error: ErrorReceiver) -> Self {
+    pub(crate) fn new(
+        number: u16,
+        stream: DuplexStream,
+        error: ErrorReceiver,
+        guard: Arc<SessionGuard>,
+    ) -> Self {
         Port {
+            number,
             stream: Some(stream),
             error: Some(error),
+            _guard: guard,
         }
     }
 
+    /// Port number being forwarded.
+    pub fn number(&self) -> u16 {
+        self.number
+    }
+
     /// Data pipe for sending to and receiving from the forwarded port.
     ///
     /// This returns a `Some` on the first call, then a `None` on every subsequent call
@@ -177,6 +550,182 @@ impl Port {
         // Ignore Cancellation error.
         self.error.take().map(|recv| recv.map(|res| res.ok()))
     }
+
+    /// Like [`Port::stream`], but wraps the duplex stream in a [`BufferedPortStream`] so small
+    /// writes are coalesced into fewer WebSocket frames and the read side gets `AsyncBufRead`
+    /// (`read_until`, `lines`, ...).
+    ///
+    /// This returns a `Some` on the first call, then a `None` on every subsequent call.
+    pub fn buffered_stream(
+        &mut self,
+    ) -> Option<BufferedPortStream<impl AsyncRead + AsyncWrite + Unpin>> {
+        self.stream().map(BufferedPortStream::new)
+    }
+}
+
+/// A buffered, vectored wrapper over a [`Port`]'s duplex stream.
+///
+/// Every small `write` on a bare [`Port::stream`] becomes its own one-byte-prefixed WebSocket
+/// binary frame, which is wasteful for line- or record-oriented protocols. `BufferedPortStream`
+/// coalesces consecutive writes into a single buffer and only forwards them to the underlying
+/// stream once the next write would take the buffer past `write_threshold` bytes, or
+/// [`AsyncWriteExt::flush`] is called explicitly. A single write of `write_threshold` bytes or
+/// more goes straight to the underlying stream. On the read side, it implements [`AsyncBufRead`] by buffering whatever the
+/// underlying stream hands back from a single read, so callers can use `read_until`/`lines`
+/// without each call reissuing a read on the duplex stream.
+///
+/// Remains a transparent `AsyncRead + AsyncWrite`, so it's a drop-in replacement for the stream
+/// returned by [`Port::stream`].
+pub struct BufferedPortStream<T> {
+    inner: T,
+    // Backing storage for `read_state`, reused across reads rather than reallocated per call.
+    read_buf: Vec<u8>,
+    read_state: ReadState,
+    write_buf: Vec<u8>,
+    write_threshold: usize,
+}
+
+enum ReadState {
+    Ready { offset: usize, filled: usize },
+    Pending,
+}
+
+impl<T> BufferedPortStream<T> {
+    /// Wrap `inner`, coalescing writes until they reach [`DEFAULT_BUFFER_SIZE`] bytes.
+    pub fn new(inner: T) -> Self {
+        Self::with_write_threshold(inner, DEFAULT_BUFFER_SIZE)
+    }
+
+    /// Wrap `inner`, coalescing writes until they reach `write_threshold` bytes.
+    pub fn with_write_threshold(inner: T, write_threshold: usize) -> Self {
+        BufferedPortStream {
+            inner,
+            read_buf: vec![0; DEFAULT_BUFFER_SIZE],
+            read_state: ReadState::Pending,
+            write_buf: Vec::new(),
+            write_threshold,
+        }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncBufRead for BufferedPortStream<T> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        if matches!(this.read_state, ReadState::Ready { offset, filled } if offset < filled) {
+            // Fall through to returning the buffered data below.
+        } else {
+            let mut read_buf = ReadBuf::new(&mut this.read_buf);
+            ready!(Pin::new(&mut this.inner).poll_read(cx, &mut read_buf))?;
+            let filled = read_buf.filled().len();
+            this.read_state = ReadState::Ready { offset: 0, filled };
+        }
+
+        match this.read_state {
+            ReadState::Ready { offset, filled } => Poll::Ready(Ok(&this.read_buf[offset..filled])),
+            ReadState::Pending => unreachable!("just set to Ready above"),
+        }
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.get_mut();
+        if let ReadState::Ready { offset, filled } = &mut this.read_state {
+            *offset += amt;
+            if *offset >= *filled {
+                this.read_state = ReadState::Pending;
+            }
+        }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for BufferedPortStream<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let available = ready!(self.as_mut().poll_fill_buf(cx))?;
+        let amt = std::cmp::min(available.len(), buf.remaining());
+        buf.put_slice(&available[..amt]);
+        self.consume(amt);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for BufferedPortStream<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        // Flush first if `buf` would overflow the threshold, and *before* staging any of `buf`
+        // into `write_buf`: if the flush returns `Pending`, the `AsyncWrite` contract requires
+        // the caller to retry with the same `buf`, and we must not have already buffered it.
+        if !this.write_buf.is_empty() && this.write_buf.len() + buf.len() > this.write_threshold {
+            ready!(poll_flush_write_buf(
+                Pin::new(&mut this.inner),
+                &mut this.write_buf,
+                cx
+            ))?;
+        }
+        // A write that alone reaches or exceeds the threshold bypasses the buffer entirely rather than
+        // being staged only to be immediately flushed.
+        if buf.len() >= this.write_threshold {
+            return Pin::new(&mut this.inner).poll_write(cx, buf);
+        }
+        this.write_buf.extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(poll_flush_write_buf(
+            Pin::new(&mut this.inner),
+            &mut this.write_buf,
+            cx
+        ))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(poll_flush_write_buf(
+            Pin::new(&mut this.inner),
+            &mut this.write_buf,
+            cx
+        ))?;
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
+
+// Drain `write_buf` into `inner`, blocking (in the `Poll` sense) until it's empty.
+fn poll_flush_write_buf<T: AsyncWrite>(
+    mut inner: Pin<&mut T>,
+    write_buf: &mut Vec<u8>,
+    cx: &mut Context<'_>,
+) -> Poll<io::Result<()>> {
+    let mut written = 0;
+    let result = loop {
+        if written == write_buf.len() {
+            break Ok(());
+        }
+        match inner.as_mut().poll_write(cx, &write_buf[written..]) {
+            Poll::Ready(Ok(0)) => {
+                break Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write buffered data",
+                ));
+            }
+            Poll::Ready(Ok(n)) => written += n,
+            Poll::Ready(Err(e)) => break Err(e),
+            Poll::Pending => {
+                write_buf.drain(..written);
+                return Poll::Pending;
+            }
+        }
+    };
+    write_buf.drain(..written);
+    Poll::Ready(result)
 }
 
 async fn start_message_loop<S>(
@@ -184,29 +733,52 @@ async fn start_message_loop<S>(
     ports: Vec<u16>,
     duplexes: Vec<DuplexStream>,
     error_senders: Vec<Option<ErrorSender>>,
+    channel_capacity: usize,
+    abort: oneshot::Receiver<()>,
 ) -> Result<(), Error>
 where
     S: AsyncRead + AsyncWrite + Unpin + Sized + Send + 'static,
 {
     let mut writers = Vec::new();
-    // Loops to run concurrently.
-    // We can spawn tasks to run `to_pod_loop` in parallel and flatten the errors, but the other 2 loops
-    // are over a single WebSocket connection and cannot process each port in parallel.
-    let mut loops = Vec::with_capacity(ports.len() + 2);
+    // Loops to run concurrently with `forwarder_loop`: one `to_pod_loop` per port (joined below),
+    // and the WebSocket reader (the WebSocket reader and writer cannot process each port in
+    // parallel, since they share one connection). The abort signal is watched inside
+    // `forwarder_loop` itself. Once `forwarder_loop` returns, the session is over and the other
+    // loops are dropped rather than awaited, since a `to_pod_loop` only ends once the caller drops
+    // or shuts down its stream.
+    let mut loops = Vec::with_capacity(2);
     // Channel to communicate with the main loop
-    let (sender, receiver) = mpsc::channel::<Message>(1);
+    let (sender, receiver) = mpsc::channel::<Message>(channel_capacity);
+    let mut to_pod_loops = Vec::with_capacity(ports.len());
     for (i, (r, w)) in duplexes.into_iter().map(tokio::io::split).enumerate() {
         writers.push(w);
         // Each port uses 2 channels. Duplex data channel and error.
         let ch = 2 * (i as u8);
-        loops.push(to_pod_loop(ch, r, sender.clone()).boxed());
+        to_pod_loops.push(to_pod_loop(ch, r, sender.clone()).boxed());
     }
 
+    // Once every `to_pod_loop` has hit EOF, the client is done writing, so let the server know
+    // by half-closing the WebSocket rather than leaving it to linger.
+    let mut to_pod_done_sender = sender.clone();
+    loops.push(
+        async move {
+            future::try_join_all(to_pod_loops).await?;
+            // Ignore the error: it only means `forwarder_loop` already shut down on its own.
+            let _ = to_pod_done_sender.send(Message::ToPodDone).await;
+            Ok(())
+        }
+        .boxed(),
+    );
+
     let (ws_sink, ws_stream) = stream.split();
     loops.push(from_pod_loop(ws_stream, sender).boxed());
-    loops.push(forwarder_loop(&ports, receiver, ws_sink, writers, error_senders).boxed());
+    let forwarder = forwarder_loop(&ports, receiver, ws_sink, writers, error_senders, abort).boxed();
 
-    future::try_join_all(loops).await.map(|_| ())
+    match future::select(forwarder, future::try_join_all(loops).boxed()).await {
+        future::Either::Left((result, _)) => result,
+        future::Either::Right((Ok(_), forwarder)) => forwarder.await,
+        future::Either::Right((Err(err), _)) => Err(err),
+    }
 }
 
 async fn to_pod_loop(
@@ -221,11 +793,9 @@ async fn to_pod_loop(
         .transpose()
         .map_err(Error::ReadBytesToSend)?
     {
-        if !bytes.is_empty() {
-            sender
-                .send(Message::ToPod(ch, bytes))
-                .await
-                .map_err(Error::ForwardToPod)?;
+        if !bytes.is_empty() && sender.send(Message::ToPod(ch, bytes)).await.is_err() {
+            // `forwarder_loop` is gone because the session is already tearing down.
+            return Ok(());
         }
     }
     Ok(())
@@ -253,6 +823,10 @@ where
                     .await
                     .map_err(Error::ForwardFromPod)?;
             }
+            ws::Message::Close(_) => {
+                let _ = sender.send(Message::Shutdown).await;
+                return Ok(());
+            }
             // REVIEW should we error on unexpected websocket message?
             _ => {}
         }
@@ -264,19 +838,33 @@ where
 // On `Message::ToPod(ch, bytes)`, a WebSocket message is sent with the channel prefix.
 // On `Message::FromPod(ch, bytes)` with an even `ch`, `bytes` are written to the port's sink.
 // On `Message::FromPod(ch, bytes)` with an odd `ch`, an error message is sent to the error channel of the port.
+// On `Message::ToPodDone`, a `Close` frame is sent and the loop continues, waiting for the server.
+// On `Message::Shutdown`, a `Close` frame is sent (unless one already was) and the loop returns.
+// `abort` resolving (the `Portforwarder` was aborted or dropped) is treated the same as receiving
+// `Message::Shutdown`.
 async fn forwarder_loop<S>(
     ports: &[u16],
     mut receiver: mpsc::Receiver<Message>,
     mut ws_sink: futures::stream::SplitSink<WebSocketStream<S>, ws::Message>,
     mut writers: Vec<tokio::io::WriteHalf<DuplexStream>>,
     mut error_senders: Vec<Option<ErrorSender>>,
+    mut abort: oneshot::Receiver<()>,
 ) -> Result<(), Error>
 where
     S: AsyncRead + AsyncWrite + Unpin + Sized + Send + 'static,
 {
     // Keep track if the channel has received the initialization frame.
     let mut initialized = vec![false; 2 * ports.len()];
-    while let Some(msg) = receiver.next().await {
+    // Whether a `Close` frame has already been sent on `ws_sink`.
+    let mut closed = false;
+    loop {
+        let msg = tokio::select! {
+            msg = receiver.next() => match msg {
+                Some(msg) => msg,
+                None => return Ok(()),
+            },
+            _ = &mut abort => Message::Shutdown,
+        };
         match msg {
             Message::FromPod(ch, mut bytes) => {
                 let ch = ch as usize;
@@ -329,7 +917,28 @@ where
                     .await
                     .map_err(Error::SendWebSocketMessage)?;
             }
+
+            Message::ToPodDone => {
+                if !closed {
+                    closed = true;
+                    ws_sink
+                        .send(ws::Message::Close(None))
+                        .await
+                        .map_err(Error::SendWebSocketMessage)?;
+                    ws_sink.flush().await.map_err(Error::SendWebSocketMessage)?;
+                }
+            }
+
+            Message::Shutdown => {
+                if !closed {
+                    ws_sink
+                        .send(ws::Message::Close(None))
+                        .await
+                        .map_err(Error::SendWebSocketMessage)?;
+                    ws_sink.flush().await.map_err(Error::SendWebSocketMessage)?;
+                }
+                return Ok(());
+            }
         }
     }
-    Ok(())
 }